@@ -0,0 +1,134 @@
+//! `cachepot-dist`: stands up either the scheduler or a build server half
+//! of the dist cluster, depending on the subcommand.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Placeholder [`cachepot::dist::ServerIncoming`] for
+/// [`cachepot::config::server::BuilderType::Overlay`]: the actual bubblewrap
+/// sandbox invocation isn't implemented in this build, so every job fails
+/// with a clear error instead of silently no-opping. Only the test
+/// harness's `add_custom_server` (an in-process, test-supplied handler) can
+/// run real jobs today.
+struct OverlaySandbox;
+
+impl cachepot::dist::ServerIncoming for OverlaySandbox {
+    fn handle_compile(&self, _job_id: cachepot::dist::JobId) -> cachepot::Result<()> {
+        anyhow::bail!("overlay/bwrap sandbox execution is not implemented in this build")
+    }
+}
+
+enum Command {
+    Scheduler { config: PathBuf },
+    Server { config: PathBuf },
+    Tail { url: reqwest::Url },
+}
+
+fn parse_args() -> Command {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("scheduler") => Command::Scheduler {
+            config: next_config_arg(args),
+        },
+        Some("server") => Command::Server {
+            config: next_config_arg(args),
+        },
+        Some("--tail") => Command::Tail {
+            url: next_url_arg(args),
+        },
+        _ => {
+            eprintln!("usage: cachepot-dist <scheduler|server> --config <path>");
+            eprintln!("       cachepot-dist --tail <scheduler-or-server-url>");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn next_config_arg(mut args: impl Iterator<Item = String>) -> PathBuf {
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return PathBuf::from(args.next().expect("--config needs a value"));
+        }
+    }
+    eprintln!("missing --config <path>");
+    std::process::exit(1);
+}
+
+fn next_url_arg(mut args: impl Iterator<Item = String>) -> reqwest::Url {
+    match args.next() {
+        Some(url) => url.parse().unwrap_or_else(|e| {
+            eprintln!("invalid --tail url {:?}: {}", url, e);
+            std::process::exit(1);
+        }),
+        None => {
+            eprintln!("missing --tail <url>");
+            std::process::exit(1);
+        }
+    }
+}
+
+// `Scheduler::start`/`Server::start` never return on success (their `Ok`
+// type is the uninhabited `void::Void`), so the `void::unreachable` calls
+// below are genuinely unreachable - that's the point, not a bug.
+#[allow(unreachable_code)]
+fn main() {
+    match parse_args() {
+        Command::Scheduler { config } => {
+            let cfg: cachepot::config::scheduler::Config =
+                serde_json::from_slice(&std::fs::read(config).unwrap()).unwrap();
+            let scheduler = cachepot::dist::http::Scheduler::new(
+                cfg.public_addr,
+                // Default cache size until a client/operator overrides it
+                // via `PUT /v2/daemon`.
+                10 * 1024 * 1024 * 1024,
+                cfg.client_auth,
+                cfg.server_auth,
+            )
+            .unwrap();
+            // Installed after construction (rather than a blanket
+            // `env_logger::init()`) so every `log`/`trace!`/`warn!` call
+            // also lands in the `GET /logs` ring buffer, not just stderr.
+            scheduler.logs().install().unwrap();
+            void::unreachable(scheduler.start().unwrap())
+        }
+        Command::Server { config } => {
+            let cfg: cachepot::config::server::Config =
+                serde_json::from_slice(&std::fs::read(config).unwrap()).unwrap();
+            let public_addr = cfg.public_addr;
+            let scheduler_auth_token = match &cfg.scheduler_auth {
+                cachepot::config::server::SchedulerAuth::Insecure => String::new(),
+                cachepot::config::server::SchedulerAuth::Token { token } => {
+                    cachepot::dist::http::server_auth_token(
+                        cachepot::dist::ServerId::new(public_addr),
+                        token,
+                    )
+                }
+            };
+            let cachepot::config::server::BuilderType::Overlay { hooks, .. } = cfg.builder;
+            let server = cachepot::dist::http::Server::with_timeout(
+                public_addr,
+                cfg.scheduler_url.to_url(),
+                scheduler_auth_token,
+                OverlaySandbox,
+                cfg.net_timeout_ms,
+                hooks,
+                cfg.auth,
+                cfg.transport,
+            )
+            .unwrap();
+            server.logs().install().unwrap();
+            // Printed unconditionally (cheap, and harmless under the Docker
+            // path, which doesn't look for it) so `--print-listening-addr`
+            // callers - namely `dist::ssh::SshServer::spawn` - can parse the
+            // bound address off stdout before the server starts serving.
+            println!("LISTENING {}", public_addr);
+            std::io::stdout().flush().unwrap();
+            void::unreachable(server.start().unwrap())
+        }
+        Command::Tail { url } => {
+            for line in cachepot::dist::http::fetch_logs(&url).unwrap() {
+                println!("{}", line);
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+//! `cachepot`: the client binary. Wraps a compiler invocation, consulting
+//! the local daemon (starting it if necessary) to serve the result from
+//! cache or farm it out to the dist cluster.
+
+use cachepot::exitcode::{self, FailureReason};
+
+fn run() -> Result<(), FailureReason> {
+    // Compiler invocation, cache lookup and dist dispatch live elsewhere;
+    // this stub only wires up the failure -> exit code mapping.
+    Err(FailureReason::CompilerError)
+}
+
+fn main() {
+    env_logger::init();
+    let result = std::panic::catch_unwind(run);
+    let reason = match result {
+        Ok(Ok(())) => std::process::exit(0),
+        Ok(Err(reason)) => reason,
+        Err(_) => FailureReason::InternalPanic,
+    };
+    std::process::exit(exitcode::exit_code_for(reason));
+}
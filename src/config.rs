@@ -0,0 +1,211 @@
+//! On-disk and environment configuration for the cachepot client and the
+//! dist scheduler/server binaries.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// A validated HTTP(S) URL.
+///
+/// Kept as a distinct type (rather than a bare `String` or `reqwest::Url`)
+/// so config structs can be round-tripped through JSON/bincode while still
+/// guaranteeing the value parses as a URL.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HTTPUrl(#[serde(with = "url_serde_ish")] reqwest::Url);
+
+impl HTTPUrl {
+    pub fn from_url(u: reqwest::Url) -> Self {
+        HTTPUrl(u)
+    }
+
+    pub fn to_url(&self) -> reqwest::Url {
+        self.0.clone()
+    }
+}
+
+impl std::fmt::Display for HTTPUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+mod url_serde_ish {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(url: &reqwest::Url, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(url.as_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<reqwest::Url, D::Error> {
+        let s = String::deserialize(d)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DiskCacheConfig {
+    pub dir: PathBuf,
+    pub size: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CacheConfigs {
+    pub azure: Option<()>,
+    pub disk: Option<DiskCacheConfig>,
+    pub gcs: Option<()>,
+    pub memcached: Option<()>,
+    pub redis: Option<()>,
+    pub s3: Option<()>,
+}
+
+/// The wire transport used for toolchain/artifact transfer. Negotiated via
+/// the dist capability handshake (`dist::proto::capabilities::QUIC_TRANSPORT`)
+/// and falls back to `Https` whenever either end lacks QUIC support.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Transport {
+    #[default]
+    Https,
+    #[cfg(feature = "dist-quic")]
+    Quic,
+}
+
+impl Transport {
+    /// Whether this config asks for QUIC. Always `false` when built
+    /// without `dist-quic`, since `Quic` doesn't exist as a variant in
+    /// that build - kept as a method rather than a `match` at each call
+    /// site so callers don't need their own `cfg(feature = "dist-quic")`.
+    pub fn wants_quic(&self) -> bool {
+        #[cfg(feature = "dist-quic")]
+        {
+            matches!(self, Transport::Quic)
+        }
+        #[cfg(not(feature = "dist-quic"))]
+        {
+            false
+        }
+    }
+}
+
+/// Where the rustls-backed dist client loads its trust anchors from. See
+/// `dist::tls`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TrustStore {
+    /// The `webpki-roots` bundle baked into the binary: no OS dependency,
+    /// but misses internally-issued CAs.
+    Webpki,
+    /// The host's trust store, loaded via `rustls-native-certs`.
+    #[default]
+    Native,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DistAuth {
+    /// Accept the scheduler's self-signed certificate (rustls'
+    /// `danger_accept_invalid_certs`) and skip loading `trust_store`. Only
+    /// ever set by tests.
+    pub dangerously_insecure: bool,
+    /// Trust anchors to validate the scheduler/server certificate against
+    /// when `dangerously_insecure` is `false`.
+    #[serde(default)]
+    pub trust_store: TrustStore,
+}
+
+impl Default for DistAuth {
+    fn default() -> Self {
+        DistAuth {
+            dangerously_insecure: true,
+            trust_store: TrustStore::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DistConfig {
+    pub auth: DistAuth,
+    pub scheduler_url: Option<HTTPUrl>,
+    pub cache_dir: PathBuf,
+    pub toolchains: Vec<PathBuf>,
+    pub toolchain_cache_size: u64,
+    /// TODO: only rewrite `#include`s for toolchains we know need it.
+    pub rewrite_includes_only: bool,
+    /// Bounds every scheduler/server RPC the client makes. `0` means wait
+    /// indefinitely, for users on very slow links; defaults to `0` so
+    /// existing configs keep their current (unbounded) behavior.
+    #[serde(default)]
+    pub net_timeout_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileConfig {
+    pub cache: CacheConfigs,
+    pub dist: DistConfig,
+}
+
+pub mod scheduler {
+    use serde::{Deserialize, Serialize};
+    use std::net::SocketAddr;
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub enum ClientAuth {
+        Insecure,
+        Token { token: String },
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub enum ServerAuth {
+        Insecure,
+        Token { token: String },
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct Config {
+        pub public_addr: SocketAddr,
+        pub client_auth: ClientAuth,
+        pub server_auth: ServerAuth,
+    }
+}
+
+pub mod server {
+    use super::HTTPUrl;
+    use serde::{Deserialize, Serialize};
+    use std::net::SocketAddr;
+    use std::path::PathBuf;
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub enum BuilderType {
+        Overlay {
+            build_dir: PathBuf,
+            bwrap_path: PathBuf,
+            /// External commands run around each sandboxed build, e.g. to
+            /// warm caches or mount secrets. See [`crate::dist::hooks`].
+            #[serde(default)]
+            hooks: crate::dist::hooks::SandboxHooks,
+        },
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub enum SchedulerAuth {
+        Insecure,
+        Token { token: String },
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct Config {
+        pub builder: BuilderType,
+        pub cache_dir: PathBuf,
+        pub public_addr: SocketAddr,
+        pub scheduler_url: HTTPUrl,
+        pub scheduler_auth: SchedulerAuth,
+        pub toolchain_cache_size: u64,
+        #[serde(default)]
+        pub transport: super::Transport,
+        /// Bounds every scheduler RPC this server makes (registration,
+        /// heartbeats). `0` means wait indefinitely.
+        #[serde(default)]
+        pub net_timeout_ms: u64,
+        /// Trust anchors/insecure-cert handling for this server's rustls
+        /// client to the scheduler. See [`crate::dist::tls`].
+        #[serde(default)]
+        pub auth: super::DistAuth,
+    }
+}
@@ -0,0 +1,107 @@
+//! Exponential backoff with full jitter, used anywhere cachepot polls for
+//! readiness or retries a dist RPC against a possibly-still-starting or
+//! transiently-unreachable peer.
+//!
+//! Using the *sampled* delay (rather than the raw exponential one) avoids a
+//! thundering herd when many clients reconnect to a scheduler at once, e.g.
+//! right after it restarts.
+
+use std::time::Duration;
+
+use rand::Rng;
+
+/// A single backoff/retry policy: attempt `n` waits
+/// `min(max_delay, base_delay * 2^n)`, sampled uniformly from `[0, delay]`.
+#[derive(Clone, Copy, Debug)]
+pub struct Backoff {
+    base_delay: Duration,
+    max_delay: Duration,
+    max_attempts: u32,
+}
+
+impl Backoff {
+    pub fn new(base_delay: Duration, max_delay: Duration, max_attempts: u32) -> Self {
+        Backoff {
+            base_delay,
+            max_delay,
+            max_attempts,
+        }
+    }
+
+    /// The full-jitter delay to sleep before retry attempt `n` (0-indexed).
+    pub fn delay_for_attempt(&self, n: u32) -> Duration {
+        let exp = 2u32.saturating_pow(n);
+        let cap = self
+            .base_delay
+            .saturating_mul(exp)
+            .min(self.max_delay)
+            .as_millis()
+            .min(u64::MAX as u128) as u64;
+        let jittered = if cap == 0 {
+            0
+        } else {
+            rand::thread_rng().gen_range(0..=cap)
+        };
+        Duration::from_millis(jittered)
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+}
+
+/// Error returned once a [`Backoff`]-governed retry loop exhausts its
+/// attempts, carrying the last underlying error for diagnostics.
+#[derive(thiserror::Error, Debug)]
+#[error("gave up after {attempts} attempts, last error: {last_error}")]
+pub struct RetriesExhausted {
+    pub attempts: u32,
+    pub last_error: String,
+}
+
+/// Calls `f` until it returns `Ok`, sleeping a full-jitter backoff delay
+/// between attempts, up to `backoff.max_attempts()` tries.
+pub fn retry_with_backoff<T, E: std::fmt::Display>(
+    backoff: Backoff,
+    mut f: impl FnMut() -> Result<T, E>,
+) -> Result<T, RetriesExhausted> {
+    let mut last_error = String::new();
+    for attempt in 0..backoff.max_attempts() {
+        match f() {
+            Ok(v) => return Ok(v),
+            Err(e) => last_error = e.to_string(),
+        }
+        if attempt + 1 < backoff.max_attempts() {
+            std::thread::sleep(backoff.delay_for_attempt(attempt));
+        }
+    }
+    Err(RetriesExhausted {
+        attempts: backoff.max_attempts(),
+        last_error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_never_exceeds_max() {
+        let b = Backoff::new(Duration::from_millis(10), Duration::from_millis(100), 10);
+        for n in 0..20 {
+            assert!(b.delay_for_attempt(n) <= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn retry_gives_up_after_max_attempts() {
+        let b = Backoff::new(Duration::from_millis(1), Duration::from_millis(1), 3);
+        let mut calls = 0;
+        let result: Result<(), RetriesExhausted> = retry_with_backoff(b, || {
+            calls += 1;
+            Err::<(), _>("nope")
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+    }
+}
@@ -0,0 +1,130 @@
+//! Single-flight coalescing for duplicate dist jobs: when a clean CI
+//! fan-out submits N identical jobs (same toolchain + preprocessed input
+//! hash), only the first is scheduled - the rest subscribe to its result.
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::dist::ServerId;
+
+/// Content hash identifying a compile job: same toolchain + same
+/// preprocessed input always produces the same key.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub struct JobKey(pub [u8; 32]);
+
+/// The terminal outcome of a coalesced job, broadcast to every waiter.
+/// A failed or panicked job still sends this (as `Err`) rather than
+/// dropping the sender, so no waiter is left hanging.
+pub type JobResult = Result<ServerId, String>;
+
+/// Coalesces concurrent submissions of the same job: the first submitter
+/// schedules the work and every subsequent submitter for the same
+/// [`JobKey`] subscribes to the same result instead of triggering a
+/// redundant compilation.
+pub struct JobCoalescer {
+    inflight: DashMap<JobKey, broadcast::Sender<JobResult>>,
+}
+
+impl Default for JobCoalescer {
+    fn default() -> Self {
+        JobCoalescer {
+            inflight: DashMap::new(),
+        }
+    }
+}
+
+/// What the caller should do after calling [`JobCoalescer::submit`].
+pub enum Submission {
+    /// No job was in flight for this key - the caller owns scheduling it
+    /// and must call [`JobCoalescer::finish`] with the outcome exactly
+    /// once.
+    Lead(JobKey),
+    /// A job was already in flight; await this receiver instead of
+    /// scheduling new work.
+    Follow(broadcast::Receiver<JobResult>),
+}
+
+impl JobCoalescer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Atomically checks for (and, if absent, creates) an in-flight entry
+    /// for `key`. Uses the `Entry` API under DashMap's per-shard lock so
+    /// the check-and-insert can't race with a concurrent `submit` or
+    /// `finish` for the same key.
+    pub fn submit(&self, key: JobKey) -> Submission {
+        match self.inflight.entry(key) {
+            dashmap::mapref::entry::Entry::Occupied(entry) => {
+                Submission::Follow(entry.get().subscribe())
+            }
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                let (tx, _rx) = broadcast::channel(1);
+                entry.insert(tx);
+                Submission::Lead(key)
+            }
+        }
+    }
+
+    /// Blocks the calling thread for a follower's result, for callers (like
+    /// the scheduler's synchronous HTTP handler) that aren't themselves
+    /// running inside a tokio runtime.
+    pub fn blocking_recv(mut rx: broadcast::Receiver<JobResult>) -> JobResult {
+        futures::executor::block_on(rx.recv())
+            .unwrap_or_else(|_| Err("coalesced job sender dropped without a result".to_owned()))
+    }
+
+    /// Called exactly once by the submitter that got [`Submission::Lead`],
+    /// with the job's terminal result (including on failure/panic, so
+    /// followers don't hang). Removes the entry so a later retry for the
+    /// same key isn't permanently glued to this result.
+    pub fn finish(&self, key: JobKey, result: JobResult) {
+        if let Some((_, tx)) = self.inflight.remove(&key) {
+            // No receivers left (e.g. all followers gave up) is fine - the
+            // point was just to notify whoever's still listening.
+            let _ = tx.send(result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(n: u8) -> JobKey {
+        let mut bytes = [0u8; 32];
+        bytes[0] = n;
+        JobKey(bytes)
+    }
+
+    #[test]
+    fn second_submit_for_same_key_follows() {
+        let coalescer = JobCoalescer::new();
+        let k = key(1);
+        assert!(matches!(coalescer.submit(k), Submission::Lead(_)));
+        assert!(matches!(coalescer.submit(k), Submission::Follow(_)));
+    }
+
+    #[test]
+    fn finish_removes_the_entry_so_a_retry_is_not_glued_to_a_stale_result() {
+        let coalescer = JobCoalescer::new();
+        let k = key(2);
+        assert!(matches!(coalescer.submit(k), Submission::Lead(_)));
+        coalescer.finish(k, Err("boom".to_owned()));
+        assert!(matches!(coalescer.submit(k), Submission::Lead(_)));
+    }
+
+    #[tokio::test]
+    async fn a_failed_job_still_notifies_followers() {
+        let coalescer = JobCoalescer::new();
+        let k = key(3);
+        assert!(matches!(coalescer.submit(k), Submission::Lead(_)));
+        let mut rx = match coalescer.submit(k) {
+            Submission::Follow(rx) => rx,
+            Submission::Lead(_) => panic!("expected a follower"),
+        };
+        coalescer.finish(k, Err("compile failed".to_owned()));
+        assert_eq!(rx.recv().await.unwrap(), Err("compile failed".to_owned()));
+    }
+}
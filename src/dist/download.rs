@@ -0,0 +1,133 @@
+//! Resumable, range-based downloads for toolchain bundles and build
+//! artifacts: a dropped connection resumes from the last received byte
+//! instead of restarting the whole transfer.
+
+use std::io::{Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context;
+use reqwest::blocking::Client;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+
+use crate::dist::backoff::Backoff;
+
+/// Backoff policy governing retries of a dropped mid-transfer connection -
+/// bounded so a persistently broken connection gives up instead of
+/// hammering the server forever.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+const RETRY_MAX_ATTEMPTS: u32 = 8;
+
+/// Downloads `url` into `dest`, resuming from any partial `<dest>.partial`
+/// file left behind by a previous attempt. The partial file is only
+/// renamed into place once the transfer completes and its SHA-256
+/// checksum, if provided, matches.
+pub fn download_resumable(
+    client: &Client,
+    url: reqwest::Url,
+    dest: &Path,
+    expected_sha256: Option<&str>,
+) -> crate::Result<()> {
+    let partial_path = partial_path(dest);
+    let mut offset = partial_path.metadata().map(|m| m.len()).unwrap_or(0);
+    let backoff = Backoff::new(RETRY_BASE_DELAY, RETRY_MAX_DELAY, RETRY_MAX_ATTEMPTS);
+    let mut attempt = 0;
+
+    loop {
+        let mut request = client.get(url.clone());
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request.send()?;
+
+        match response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                verify_range_start(&response, offset)?;
+            }
+            StatusCode::OK if offset > 0 => {
+                // Server doesn't support ranges: it sent the whole body
+                // again. Fall back to a full download rather than
+                // corrupting the partial file with a double-write.
+                offset = 0;
+                std::fs::remove_file(&partial_path).ok();
+            }
+            StatusCode::OK => {}
+            status => anyhow::bail!("unexpected status downloading {}: {}", url, status),
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&partial_path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        match std::io::copy(&mut response, &mut file) {
+            Ok(_) => break,
+            Err(e) => {
+                // Dropped mid-transfer: record how much we actually got (be
+                // it a resumed transfer or - just as often - a fresh one
+                // that made it partway before dropping) and retry from
+                // there, up to `RETRY_MAX_ATTEMPTS` times with a
+                // full-jitter backoff delay between attempts so a
+                // persistently broken connection doesn't spin forever.
+                if attempt >= backoff.max_attempts() {
+                    return Err(e).context(format!(
+                        "giving up resuming download of {} after {} attempts",
+                        url, attempt
+                    ));
+                }
+                offset = file.metadata()?.len();
+                std::thread::sleep(backoff.delay_for_attempt(attempt));
+                attempt += 1;
+                continue;
+            }
+        }
+    }
+
+    if let Some(expected) = expected_sha256 {
+        verify_checksum(&partial_path, expected)?;
+    }
+    std::fs::rename(&partial_path, dest)?;
+    Ok(())
+}
+
+fn partial_path(dest: &Path) -> std::path::PathBuf {
+    let mut partial = dest.as_os_str().to_owned();
+    partial.push(".partial");
+    partial.into()
+}
+
+fn verify_range_start(response: &reqwest::blocking::Response, offset: u64) -> crate::Result<()> {
+    let content_range = response
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if !content_range.starts_with(&format!("bytes {}-", offset)) {
+        anyhow::bail!(
+            "server returned unexpected Content-Range {:?} for requested offset {}",
+            content_range,
+            offset
+        );
+    }
+    Ok(())
+}
+
+fn verify_checksum(path: &Path, expected_sha256: &str) -> crate::Result<()> {
+    use sha2::{Digest, Sha256};
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    let actual = format!("{:x}", hasher.finalize());
+    if actual != expected_sha256 {
+        anyhow::bail!(
+            "checksum mismatch downloading {}: expected {}, got {}",
+            path.display(),
+            expected_sha256,
+            actual
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,219 @@
+//! OCI-style lifecycle hooks for the build sandbox.
+//!
+//! Operators can wire external commands into three points of a sandboxed
+//! build's life (analogous to OCI runtime hooks): [`HookPhase::PreCreate`]
+//! (before the sandbox/remote process is spawned), [`HookPhase::PostStart`]
+//! (once it's confirmed up) and [`HookPhase::PreStop`] (before teardown
+//! begins). Each hook is an external command that receives the current
+//! job/container state as JSON on its stdin - useful for warming caches,
+//! mounting secrets, or emitting metrics around a build.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// A point in the sandbox's lifecycle a hook can be attached to.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum HookPhase {
+    PreCreate,
+    PostStart,
+    PreStop,
+}
+
+impl HookPhase {
+    fn name(self) -> &'static str {
+        match self {
+            HookPhase::PreCreate => "pre-create",
+            HookPhase::PostStart => "post-start",
+            HookPhase::PreStop => "pre-stop",
+        }
+    }
+}
+
+/// One external command run at a given [`HookPhase`]. The command is
+/// invoked with the job state (as JSON) written to its stdin, and must
+/// exit within `timeout_ms` or it's killed and treated as a failure.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HookConfig {
+    pub command: Vec<String>,
+    pub timeout_ms: u64,
+    /// A failing or timed-out required hook aborts job setup/teardown; a
+    /// non-required one is logged and skipped over.
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+/// The hooks configured for each lifecycle phase of the build sandbox.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SandboxHooks {
+    #[serde(default)]
+    pub pre_create: Vec<HookConfig>,
+    #[serde(default)]
+    pub post_start: Vec<HookConfig>,
+    #[serde(default)]
+    pub pre_stop: Vec<HookConfig>,
+}
+
+impl SandboxHooks {
+    fn for_phase(&self, phase: HookPhase) -> &[HookConfig] {
+        match phase {
+            HookPhase::PreCreate => &self.pre_create,
+            HookPhase::PostStart => &self.post_start,
+            HookPhase::PreStop => &self.pre_stop,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum HookError {
+    #[error("hook command must not be empty")]
+    EmptyCommand,
+    #[error("hook {command:?} timed out after {timeout_ms}ms")]
+    Timeout {
+        command: Vec<String>,
+        timeout_ms: u64,
+    },
+    #[error("hook {command:?} exited with {status}")]
+    NonZeroExit {
+        command: Vec<String>,
+        status: std::process::ExitStatus,
+    },
+    #[error("failed to run hook {command:?}: {source}")]
+    Spawn {
+        command: Vec<String>,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Runs every hook configured for `phase`, in order, passing `state_json`
+/// on each hook's stdin. The first failure from a `required` hook aborts
+/// and is returned; failures from non-required hooks are logged and the
+/// remaining hooks still run.
+pub fn run_hooks(hooks: &SandboxHooks, phase: HookPhase, state_json: &str) -> crate::Result<()> {
+    for hook in hooks.for_phase(phase) {
+        match run_hook(hook, state_json) {
+            Ok(()) => {}
+            Err(e) if hook.required => {
+                return Err(anyhow::anyhow!("{} hook failed: {}", phase.name(), e));
+            }
+            Err(e) => {
+                warn!(
+                    "{} hook {:?} failed (not required, continuing): {}",
+                    phase.name(),
+                    hook.command,
+                    e
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_hook(hook: &HookConfig, state_json: &str) -> Result<(), HookError> {
+    let (program, args) = hook.command.split_first().ok_or(HookError::EmptyCommand)?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|source| HookError::Spawn {
+            command: hook.command.clone(),
+            source,
+        })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(state_json.as_bytes());
+    }
+
+    let deadline = Instant::now() + Duration::from_millis(hook.timeout_ms);
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                return if status.success() {
+                    Ok(())
+                } else {
+                    Err(HookError::NonZeroExit {
+                        command: hook.command.clone(),
+                        status,
+                    })
+                };
+            }
+            Ok(None) => {
+                if Instant::now() >= deadline {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(HookError::Timeout {
+                        command: hook.command.clone(),
+                        timeout_ms: hook.timeout_ms,
+                    });
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Err(source) => {
+                return Err(HookError::Spawn {
+                    command: hook.command.clone(),
+                    source,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(command: &[&str], timeout_ms: u64, required: bool) -> HookConfig {
+        HookConfig {
+            command: command.iter().map(|s| s.to_string()).collect(),
+            timeout_ms,
+            required,
+        }
+    }
+
+    #[test]
+    fn required_hook_failure_aborts() {
+        let hooks = SandboxHooks {
+            pre_create: vec![hook(&["false"], 1000, true)],
+            ..Default::default()
+        };
+        assert!(run_hooks(&hooks, HookPhase::PreCreate, "{}").is_err());
+    }
+
+    #[test]
+    fn non_required_hook_failure_is_swallowed() {
+        let hooks = SandboxHooks {
+            pre_create: vec![hook(&["false"], 1000, false)],
+            ..Default::default()
+        };
+        assert!(run_hooks(&hooks, HookPhase::PreCreate, "{}").is_ok());
+    }
+
+    #[test]
+    fn empty_command_errors_instead_of_panicking() {
+        let hooks = SandboxHooks {
+            pre_create: vec![hook(&[], 1000, true)],
+            ..Default::default()
+        };
+        let err = run_hooks(&hooks, HookPhase::PreCreate, "{}").unwrap_err();
+        assert!(err.to_string().contains("hook command must not be empty"));
+    }
+
+    #[test]
+    fn slow_required_hook_times_out() {
+        let hooks = SandboxHooks {
+            pre_stop: vec![hook(&["sleep", "5"], 50, true)],
+            ..Default::default()
+        };
+        let err = run_hooks(&hooks, HookPhase::PreStop, "{}").unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+    }
+}
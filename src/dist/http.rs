@@ -0,0 +1,963 @@
+//! The HTTP transport for the dist protocol: the scheduler's endpoints for
+//! server registration/job dispatch and the build server's endpoint for
+//! accepting jobs from the scheduler.
+//!
+//! Wire format is bincode over HTTP bodies; JSON is reserved for the
+//! human/operator-facing management surface (the `/v2` endpoints, `/logs`
+//! and the `/status` event payloads).
+
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::scheduler::{ClientAuth, ServerAuth};
+use crate::config::{DistAuth, Transport};
+use crate::dist::coalesce::{JobCoalescer, JobKey, Submission};
+use crate::dist::hooks::{run_hooks, HookPhase, SandboxHooks};
+use crate::dist::logbuf::LogBuffer;
+use crate::dist::proto::{capabilities, negotiate, Handshake, ProtocolError};
+use crate::dist::sse::StatusStream;
+use crate::dist::{JobId, SchedulerStatusResult, ServerId, ServerIncoming};
+
+/// How often a subscribed `/status` stream gets a fresh snapshot.
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Number of recent log lines kept in memory for `GET /logs`.
+const LOG_BUFFER_CAPACITY: usize = 4096;
+
+/// A `net_timeout_ms` config value of `0` means "wait indefinitely".
+pub(crate) const NO_TIMEOUT: u64 = 0;
+
+/// Builds the `reqwest` client used for scheduler/server RPCs, bounding
+/// every request by `timeout_ms` (`0` disables the bound, for users on
+/// very slow links).
+pub fn client_with_timeout(timeout_ms: u64) -> crate::Result<reqwest::blocking::Client> {
+    let mut builder = reqwest::blocking::Client::builder();
+    if timeout_ms != NO_TIMEOUT {
+        builder = builder.timeout(Duration::from_millis(timeout_ms));
+    }
+    Ok(builder.build()?)
+}
+
+/// Distinguishes a genuine request timeout from a connection refusal (or
+/// any other transport error), so callers can retry/fall back to local
+/// accordingly instead of treating every failure the same way.
+#[derive(thiserror::Error, Debug)]
+pub enum RpcError {
+    #[error("request to {url} timed out after {timeout_ms}ms")]
+    Timeout { url: String, timeout_ms: u64 },
+    #[error("request to {url} failed: {source}")]
+    Other {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+}
+
+/// Wraps a `reqwest::Error` from a request made with the given timeout,
+/// classifying it into [`RpcError::Timeout`] vs. [`RpcError::Other`].
+pub fn classify_rpc_error(url: &reqwest::Url, timeout_ms: u64, source: reqwest::Error) -> RpcError {
+    if source.is_timeout() {
+        RpcError::Timeout {
+            url: url.to_string(),
+            timeout_ms,
+        }
+    } else {
+        RpcError::Other {
+            url: url.to_string(),
+            source,
+        }
+    }
+}
+
+/// Build-time version string, used both in the `/v2/daemon` response and in
+/// future protocol-compatibility checks.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The bearer token a build server authenticates its `register_server`
+/// requests with, binding the scheduler's configured `server_auth` token to
+/// the server's own id so one compromised/malicious server can't replay its
+/// token as a different `ServerId`.
+pub fn server_auth_token(id: ServerId, configured_token: &str) -> String {
+    format!("{} {}", id.addr(), configured_token)
+}
+
+/// Pulls the bearer token out of a request's `Authorization` header, if any.
+fn bearer_token(request: &rouille::Request) -> Option<&str> {
+    request.header("Authorization")?.strip_prefix("Bearer ")
+}
+
+pub mod urls {
+    use reqwest::Url;
+
+    pub fn scheduler_status(scheduler_url: &Url) -> Url {
+        scheduler_url
+            .join("/api/v1/scheduler/status")
+            .expect("failed to build scheduler status url")
+    }
+
+    pub fn scheduler_daemon(scheduler_url: &Url) -> Url {
+        scheduler_url
+            .join("/v2/daemon")
+            .expect("failed to build scheduler daemon url")
+    }
+
+    pub fn scheduler_servers(scheduler_url: &Url) -> Url {
+        scheduler_url
+            .join("/v2/servers")
+            .expect("failed to build scheduler servers url")
+    }
+
+    pub fn scheduler_server_drain(scheduler_url: &Url, id: &str) -> Url {
+        scheduler_url
+            .join(&format!("/v2/servers/{}/drain", id))
+            .expect("failed to build scheduler server drain url")
+    }
+
+    pub fn scheduler_register_server(scheduler_url: &Url, id: &str) -> Url {
+        scheduler_url
+            .join(&format!("/api/v1/scheduler/register_server/{}", id))
+            .expect("failed to build scheduler register_server url")
+    }
+
+    /// Shared by the scheduler and build servers - both expose `GET /logs`.
+    pub fn logs(base_url: &Url) -> Url {
+        base_url.join("/logs").expect("failed to build logs url")
+    }
+
+    /// Shared by the scheduler and build servers - both expose a readiness
+    /// and live-state Server-Sent-Events stream at `GET /status`.
+    pub fn status(base_url: &Url) -> Url {
+        base_url.join("/status").expect("failed to build status url")
+    }
+
+    pub fn scheduler_submit_job(scheduler_url: &Url) -> Url {
+        scheduler_url
+            .join("/api/v1/scheduler/submit_job")
+            .expect("failed to build scheduler submit_job url")
+    }
+
+    pub fn scheduler_job_finish(scheduler_url: &Url, server_id: &str) -> Url {
+        scheduler_url
+            .join(&format!("/api/v1/scheduler/jobs/{}/finish", server_id))
+            .expect("failed to build scheduler job finish url")
+    }
+}
+
+/// Fetches the recent log lines from a live scheduler or build server,
+/// backing the `cachepot-dist ... --tail` client command.
+pub fn fetch_logs(base_url: &reqwest::Url) -> crate::Result<Vec<String>> {
+    Ok(reqwest::blocking::get(urls::logs(base_url))?.json()?)
+}
+
+/// Wraps a [`StatusStream`] as a chunked `text/event-stream` response.
+fn sse_response<R, S>(stream: StatusStream<R, S>) -> rouille::Response
+where
+    R: Fn() -> bool + Send + 'static,
+    S: Fn() -> String + Send + 'static,
+{
+    rouille::Response {
+        status_code: 200,
+        headers: vec![
+            ("Content-Type".into(), "text/event-stream".into()),
+            ("Cache-Control".into(), "no-cache".into()),
+        ],
+        data: rouille::ResponseBody::from_reader(stream),
+        upgrade: None,
+    }
+}
+
+/// One entry in the scheduler's view of the cluster.
+#[derive(Clone, Debug)]
+struct ServerState {
+    num_cpus: usize,
+    in_progress: usize,
+    last_heartbeat: Instant,
+    draining: bool,
+    /// Whether this server negotiated [`capabilities::QUIC_TRANSPORT`] at
+    /// its last (re-)registration.
+    quic: bool,
+}
+
+/// Build/version/uptime/config summary returned by `GET /v2/daemon`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DaemonInfo {
+    pub version: String,
+    pub uptime_secs: u64,
+    pub toolchain_cache_size: u64,
+}
+
+/// Per-server summary returned by `GET /v2/servers`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServerStatus {
+    pub id: String,
+    pub num_cpus: usize,
+    pub in_progress: usize,
+    /// `in_progress / num_cpus`, the same figure the placement policy
+    /// minimizes over, surfaced so placement decisions are observable.
+    pub load: f64,
+    pub last_heartbeat_secs_ago: u64,
+    pub draining: bool,
+    pub quic: bool,
+}
+
+/// Body accepted by `PUT /v2/daemon` to live-adjust tunables.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UpdateDaemonRequest {
+    pub toolchain_cache_size: Option<u64>,
+}
+
+/// Body a build server sends when registering (or re-registering) with the
+/// scheduler, carrying its [`Handshake`] alongside its capacity.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RegisterServerRequest {
+    pub handshake: Handshake,
+    pub num_cpus: usize,
+}
+
+struct SchedulerState {
+    servers: Mutex<HashMap<ServerId, ServerState>>,
+    toolchain_cache_size: Mutex<u64>,
+    logs: Arc<LogBuffer>,
+    coalescer: JobCoalescer,
+    start_time: Instant,
+    client_auth: ClientAuth,
+    server_auth: ServerAuth,
+}
+
+impl SchedulerState {
+    /// Checks a client-facing request (the `/v2` management API) against
+    /// the configured [`ClientAuth`].
+    fn authorize_client(&self, request: &rouille::Request) -> bool {
+        match &self.client_auth {
+            ClientAuth::Insecure => true,
+            ClientAuth::Token { token } => bearer_token(request) == Some(token.as_str()),
+        }
+    }
+
+    /// Checks a `register_server` request against the configured
+    /// [`ServerAuth`], binding the token to `id` via [`server_auth_token`]
+    /// so it can't be replayed for a different server.
+    fn authorize_server(&self, id: ServerId, request: &rouille::Request) -> bool {
+        match &self.server_auth {
+            ServerAuth::Insecure => true,
+            ServerAuth::Token { token } => {
+                bearer_token(request) == Some(server_auth_token(id, token).as_str())
+            }
+        }
+    }
+    fn status(&self) -> SchedulerStatusResult {
+        let servers = self.servers.lock().unwrap();
+        SchedulerStatusResult {
+            num_servers: servers.len(),
+            num_cpus: servers.values().map(|s| s.num_cpus).sum(),
+            in_progress: servers.values().map(|s| s.in_progress).sum(),
+        }
+    }
+
+    fn daemon_info(&self) -> DaemonInfo {
+        DaemonInfo {
+            version: VERSION.to_owned(),
+            uptime_secs: self.start_time.elapsed().as_secs(),
+            toolchain_cache_size: *self.toolchain_cache_size.lock().unwrap(),
+        }
+    }
+
+    fn server_statuses(&self) -> Vec<ServerStatus> {
+        let servers = self.servers.lock().unwrap();
+        servers
+            .iter()
+            .map(|(id, state)| ServerStatus {
+                id: id.addr().to_string(),
+                num_cpus: state.num_cpus,
+                in_progress: state.in_progress,
+                load: state.in_progress as f64 / state.num_cpus as f64,
+                last_heartbeat_secs_ago: state.last_heartbeat.elapsed().as_secs(),
+                draining: state.draining,
+                quic: state.quic,
+            })
+            .collect()
+    }
+
+    /// Marks `id` as draining: it keeps whatever jobs it currently has but
+    /// is no longer eligible for new job placement.
+    fn drain(&self, id: ServerId) -> crate::Result<()> {
+        let mut servers = self.servers.lock().unwrap();
+        let state = servers
+            .get_mut(&id)
+            .ok_or_else(|| anyhow::anyhow!("unknown server {:?}", id))?;
+        state.draining = true;
+        Ok(())
+    }
+
+    fn update_daemon(&self, req: UpdateDaemonRequest) {
+        if let Some(size) = req.toolchain_cache_size {
+            *self.toolchain_cache_size.lock().unwrap() = size;
+        }
+    }
+
+    /// Validates the server's handshake and, if compatible, adds (or
+    /// refreshes) its entry in the cluster. Returns the negotiated
+    /// capabilities (the intersection of ours and the server's), which the
+    /// caller hands back to the server so both ends agree on what's
+    /// actually usable - e.g. a server only uses QUIC for transfers once
+    /// the scheduler has confirmed it supports it too.
+    ///
+    /// Resets `in_progress`/`draining` on every (re-)registration, not
+    /// just on first contact: a server that crashes mid-job and restarts
+    /// otherwise keeps its stale `in_progress` count forever (nothing
+    /// calls `job_finished` for jobs orphaned by the crash), permanently
+    /// excluding it from placement or just misreporting its load.
+    fn register(
+        &self,
+        id: ServerId,
+        req: RegisterServerRequest,
+    ) -> Result<HashSet<String>, ProtocolError> {
+        let negotiated = negotiate(&req.handshake)?;
+        let quic = negotiated.contains(capabilities::QUIC_TRANSPORT);
+        let mut servers = self.servers.lock().unwrap();
+        servers
+            .entry(id)
+            .and_modify(|s| {
+                s.num_cpus = req.num_cpus;
+                s.last_heartbeat = Instant::now();
+                s.quic = quic;
+                s.in_progress = 0;
+                s.draining = false;
+            })
+            .or_insert(ServerState {
+                num_cpus: req.num_cpus,
+                in_progress: 0,
+                last_heartbeat: Instant::now(),
+                draining: false,
+                quic,
+            });
+        Ok(negotiated)
+    }
+
+    /// Picks the least-loaded non-draining server with spare capacity
+    /// (`in_progress / num_cpus` treats `num_cpus` as the server's
+    /// capacity), marking the job as in-progress on it. Every server at
+    /// capacity (or draining) results in [`PlacementError::Saturated`].
+    fn place_job(&self) -> Result<ServerId, PlacementError> {
+        let mut servers = self.servers.lock().unwrap();
+        let placement = servers
+            .iter()
+            .filter(|(_, s)| !s.draining && s.in_progress < s.num_cpus)
+            .min_by(|(_, a), (_, b)| {
+                let load = |s: &ServerState| s.in_progress as f64 / s.num_cpus as f64;
+                load(a)
+                    .partial_cmp(&load(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|(id, _)| *id);
+        match placement {
+            Some(id) => {
+                servers.get_mut(&id).unwrap().in_progress += 1;
+                Ok(id)
+            }
+            None => Err(PlacementError::Saturated),
+        }
+    }
+
+    /// Decrements a server's in-progress count on job completion or
+    /// timeout, so a crashed job doesn't permanently "fill" a slot.
+    fn job_finished(&self, id: ServerId) {
+        if let Some(state) = self.servers.lock().unwrap().get_mut(&id) {
+            state.in_progress = state.in_progress.saturating_sub(1);
+        }
+    }
+
+    /// Places `key`'s job, coalescing concurrent submissions of the same
+    /// key onto a single placement: only the first caller actually invokes
+    /// [`Self::place_job`], every other caller for the same key just
+    /// subscribes to its result.
+    fn submit_job(&self, key: JobKey) -> crate::dist::coalesce::JobResult {
+        match self.coalescer.submit(key) {
+            Submission::Lead(key) => {
+                let result = self.place_job().map_err(|e| e.to_string());
+                self.coalescer.finish(key, result.clone());
+                result
+            }
+            Submission::Follow(rx) => JobCoalescer::blocking_recv(rx),
+        }
+    }
+}
+
+/// Why the scheduler couldn't place an incoming job on any server.
+#[derive(thiserror::Error, Debug)]
+pub enum PlacementError {
+    #[error("every server is at capacity or draining")]
+    Saturated,
+}
+
+/// The scheduler's HTTP server: dispatches jobs to registered build
+/// servers and exposes the `/v2` management API for operators.
+pub struct Scheduler {
+    public_addr: SocketAddr,
+    state: Arc<SchedulerState>,
+}
+
+impl Scheduler {
+    pub fn new(
+        public_addr: SocketAddr,
+        toolchain_cache_size: u64,
+        client_auth: ClientAuth,
+        server_auth: ServerAuth,
+    ) -> crate::Result<Self> {
+        Ok(Scheduler {
+            public_addr,
+            state: Arc::new(SchedulerState {
+                servers: Mutex::new(HashMap::new()),
+                toolchain_cache_size: Mutex::new(toolchain_cache_size),
+                logs: Arc::new(LogBuffer::new(LOG_BUFFER_CAPACITY)),
+                coalescer: JobCoalescer::new(),
+                start_time: Instant::now(),
+                client_auth,
+                server_auth,
+            }),
+        })
+    }
+
+    /// This scheduler's [`LogBuffer`], served at `GET /logs`. Exposed so
+    /// the caller can [`LogBuffer::install`] it as the process-wide logger
+    /// before calling [`Self::start`].
+    pub fn logs(&self) -> &Arc<LogBuffer> {
+        &self.state.logs
+    }
+
+    /// Runs the scheduler's HTTP server, never returning on success.
+    ///
+    /// Listens over plain HTTP: `rouille::start_server` has no TLS support,
+    /// so despite servers and clients speaking TLS to *us* via
+    /// [`crate::dist::tls::build_client`], this listener doesn't actually
+    /// terminate it. Put a TLS-terminating proxy in front in production.
+    pub fn start(self) -> crate::Result<void::Void> {
+        let state = self.state;
+        rouille::start_server(self.public_addr, move |request| {
+            router!(request,
+                (GET) (/api/v1/scheduler/status) => {
+                    rouille::Response::from_data(
+                        "application/octet-stream",
+                        bincode::serialize(&state.status()).unwrap(),
+                    )
+                },
+                (POST) (/api/v1/scheduler/register_server/{addr}) => {
+                    let addr: String = addr;
+                    let reg: Option<RegisterServerRequest> = request
+                        .data()
+                        .and_then(|mut body| bincode::deserialize_from(&mut body).ok());
+                    match addr.parse().map(ServerId::new).ok().zip(reg) {
+                        Some((id, _)) if !state.authorize_server(id, request) => {
+                            rouille::Response::text("bad server auth token").with_status_code(401)
+                        }
+                        Some((id, reg)) => match state.register(id, reg) {
+                            Ok(negotiated) => {
+                                state.logs.push(format!("registered server {}", addr));
+                                rouille::Response::from_data(
+                                    "application/octet-stream",
+                                    bincode::serialize(&negotiated).unwrap(),
+                                )
+                            }
+                            Err(e) => rouille::Response::text(e.to_string()).with_status_code(409),
+                        },
+                        None => rouille::Response::text("bad register_server request")
+                            .with_status_code(400),
+                    }
+                },
+                (GET) (/logs) => {
+                    rouille::Response::json(&state.logs.tail())
+                },
+                (GET) (/status) => {
+                    let state = state.clone();
+                    sse_response(StatusStream::new(
+                        // The scheduler is ready to place jobs the moment
+                        // it's serving requests at all.
+                        || true,
+                        move || serde_json::to_string(&state.status()).unwrap(),
+                        STATUS_POLL_INTERVAL,
+                    ))
+                },
+                (POST) (/api/v1/scheduler/submit_job) => {
+                    let key: Option<JobKey> = request
+                        .data()
+                        .and_then(|mut body| bincode::deserialize_from(&mut body).ok());
+                    match key {
+                        Some(key) => match state.submit_job(key) {
+                            Ok(id) => rouille::Response::text(id.addr().to_string()),
+                            // Retryable: a caller can back off and resubmit
+                            // once a server frees up or a new one joins.
+                            Err(e) => rouille::Response::text(e).with_status_code(503),
+                        },
+                        None => rouille::Response::text("bad submit_job request")
+                            .with_status_code(400),
+                    }
+                },
+                (POST) (/api/v1/scheduler/jobs/{addr}/finish) => {
+                    let addr: String = addr;
+                    match addr.parse().map(ServerId::new) {
+                        Ok(id) => {
+                            state.job_finished(id);
+                            rouille::Response::text("")
+                        }
+                        Err(_) => rouille::Response::text("bad server id").with_status_code(400),
+                    }
+                },
+                (GET) (/v2/daemon) => {
+                    if !state.authorize_client(request) {
+                        return rouille::Response::text("bad client auth token").with_status_code(401);
+                    }
+                    rouille::Response::json(&state.daemon_info())
+                },
+                (PUT) (/v2/daemon) => {
+                    if !state.authorize_client(request) {
+                        return rouille::Response::text("bad client auth token").with_status_code(401);
+                    }
+                    match rouille::input::json_input::<UpdateDaemonRequest>(request) {
+                        Ok(body) => {
+                            state.update_daemon(body);
+                            rouille::Response::json(&state.daemon_info())
+                        }
+                        Err(e) => rouille::Response::text(e.to_string()).with_status_code(400),
+                    }
+                },
+                (GET) (/v2/servers) => {
+                    if !state.authorize_client(request) {
+                        return rouille::Response::text("bad client auth token").with_status_code(401);
+                    }
+                    rouille::Response::json(&state.server_statuses())
+                },
+                (POST) (/v2/servers/{id}/drain) => {
+                    if !state.authorize_client(request) {
+                        return rouille::Response::text("bad client auth token").with_status_code(401);
+                    }
+                    let id: String = id;
+                    match id.parse().map(ServerId::new) {
+                        Ok(id) => match state.drain(id) {
+                            Ok(()) => rouille::Response::text(""),
+                            Err(e) => rouille::Response::text(e.to_string()).with_status_code(404),
+                        },
+                        Err(_) => rouille::Response::text("invalid server id").with_status_code(400),
+                    }
+                },
+                _ => rouille::Response::empty_404(),
+            )
+        })
+    }
+}
+
+/// Live snapshot of a build server's own state, returned by its `/status`
+/// stream.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServerSelfStatus {
+    pub in_progress: usize,
+    pub registered: bool,
+}
+
+/// A build server's HTTP endpoint: receives jobs dispatched by the
+/// scheduler and hands them to a [`ServerIncoming`] handler.
+pub struct Server<S> {
+    public_addr: SocketAddr,
+    #[allow(dead_code)]
+    scheduler_url: reqwest::Url,
+    #[allow(dead_code)]
+    scheduler_auth_token: String,
+    handler: Arc<S>,
+    #[allow(dead_code)]
+    next_job_id: Mutex<NonZeroU32>,
+    logs: Arc<LogBuffer>,
+    net_timeout_ms: u64,
+    hooks: SandboxHooks,
+    auth: DistAuth,
+    transport: Transport,
+    registered: Arc<std::sync::atomic::AtomicBool>,
+    in_progress: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+/// Runs `job` (the actual sandboxed build) wrapped in `hooks`' lifecycle:
+/// pre-create and post-start hooks before it starts, a pre-stop hook once
+/// it's done, regardless of whether it succeeded. A failing `required` hook
+/// takes precedence over `job`'s own result.
+fn run_sandboxed_job<F: FnOnce() -> crate::Result<()>>(
+    hooks: &SandboxHooks,
+    job_id: JobId,
+    job: F,
+) -> crate::Result<()> {
+    let state = format!(r#"{{"job_id":{}}}"#, job_id.0);
+    run_hooks(hooks, HookPhase::PreCreate, &state)?;
+    run_hooks(hooks, HookPhase::PostStart, &state)?;
+    let result = job();
+    run_hooks(hooks, HookPhase::PreStop, &state)?;
+    result
+}
+
+impl<S: ServerIncoming + 'static> Server<S> {
+    pub fn new(
+        public_addr: SocketAddr,
+        scheduler_url: reqwest::Url,
+        scheduler_auth_token: String,
+        handler: S,
+    ) -> crate::Result<Self> {
+        Self::with_timeout(
+            public_addr,
+            scheduler_url,
+            scheduler_auth_token,
+            handler,
+            NO_TIMEOUT,
+            SandboxHooks::default(),
+            DistAuth::default(),
+            Transport::default(),
+        )
+    }
+
+    /// As [`Self::new`], but bounds every scheduler RPC this server makes
+    /// by `net_timeout_ms` (`0` for no bound), runs `hooks` around each
+    /// sandboxed build (as configured on [`crate::config::server::BuilderType::Overlay`]),
+    /// speaks TLS to the scheduler per `auth` (see [`crate::dist::tls`]),
+    /// and advertises `transport` in its handshake - actually used for
+    /// toolchain/artifact transfer only once the scheduler confirms it
+    /// supports it too (see [`Self::start`]).
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_timeout(
+        public_addr: SocketAddr,
+        scheduler_url: reqwest::Url,
+        scheduler_auth_token: String,
+        handler: S,
+        net_timeout_ms: u64,
+        hooks: SandboxHooks,
+        auth: DistAuth,
+        transport: Transport,
+    ) -> crate::Result<Self> {
+        Ok(Server {
+            public_addr,
+            scheduler_url,
+            scheduler_auth_token,
+            handler: Arc::new(handler),
+            next_job_id: Mutex::new(NonZeroU32::new(1).unwrap()),
+            logs: Arc::new(LogBuffer::new(LOG_BUFFER_CAPACITY)),
+            net_timeout_ms,
+            hooks,
+            auth,
+            transport,
+            registered: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            in_progress: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+        })
+    }
+
+    /// This server's [`LogBuffer`], served at `GET /logs`. Exposed so the
+    /// caller can [`LogBuffer::install`] it as the process-wide logger
+    /// before calling [`Self::start`].
+    pub fn logs(&self) -> &Arc<LogBuffer> {
+        &self.logs
+    }
+
+    /// Registers with the scheduler, exchanging [`Handshake`]s so an
+    /// incompatible peer is rejected up front rather than failing deep
+    /// inside a later bincode decode. Returns whether the scheduler also
+    /// negotiated [`capabilities::QUIC_TRANSPORT`] - `self.transport`
+    /// alone only says what this server *wants*; the scheduler might not
+    /// support it, in which case transfers stay on HTTPS.
+    ///
+    /// Retried with full-jitter backoff so a server that starts up (or
+    /// rejoins after `restart_server`) before the scheduler is reachable
+    /// ends up registered rather than permanently treated as dead.
+    fn register_with_scheduler(&self) -> crate::Result<bool> {
+        let req = RegisterServerRequest {
+            handshake: Handshake::current_with_quic(self.transport.wants_quic()),
+            num_cpus: num_cpus::get(),
+        };
+        let body = bincode::serialize(&req)?;
+        let url =
+            urls::scheduler_register_server(&self.scheduler_url, &self.public_addr.to_string());
+        let client = crate::dist::tls::build_client(self.net_timeout_ms, &self.auth)?;
+        let backoff = crate::dist::backoff::Backoff::new(
+            Duration::from_millis(100),
+            Duration::from_secs(5),
+            10,
+        );
+        let negotiated = crate::dist::backoff::retry_with_backoff(backoff, || {
+            let mut res = client
+                .post(url.clone())
+                .bearer_auth(&self.scheduler_auth_token)
+                .body(body.clone())
+                .send()
+                .map_err(|e| classify_rpc_error(&url, self.net_timeout_ms, e).to_string())?;
+            if !res.status().is_success() {
+                return Err(format!("scheduler rejected registration: {}", res.status()));
+            }
+            bincode::deserialize_from::<_, HashSet<String>>(&mut res)
+                .map_err(|e| format!("malformed register_server response: {}", e))
+        })
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+        self.registered.store(true, std::sync::atomic::Ordering::SeqCst);
+        Ok(negotiated.contains(capabilities::QUIC_TRANSPORT))
+    }
+
+    /// Runs the build server's HTTP endpoint, never returning on success.
+    ///
+    /// Listens over plain HTTP, same caveat as [`Scheduler::start`]: this
+    /// server's outbound RPCs to the scheduler go over TLS, but nothing
+    /// here terminates TLS for inbound connections.
+    pub fn start(self) -> crate::Result<void::Void> {
+        let quic_negotiated = self.register_with_scheduler()?;
+        #[cfg(feature = "dist-quic")]
+        if quic_negotiated {
+            // Both ends support it: establish the QUIC endpoint transfers
+            // will use instead of the plain-HTTPS path in this module.
+            // Bound to an ephemeral port - this is the client side of the
+            // scheduler/server's toolchain/artifact connection, not the
+            // inbound compile-job listener below.
+            let scheduler_addr = self
+                .scheduler_url
+                .host_str()
+                .zip(self.scheduler_url.port_or_known_default())
+                .and_then(|(host, port)| {
+                    use std::net::ToSocketAddrs;
+                    (host, port).to_socket_addrs().ok()?.next()
+                });
+            match (quinn::Endpoint::client(SocketAddr::from(([0, 0, 0, 0], 0))), scheduler_addr) {
+                (Ok(endpoint), Some(remote)) => {
+                    let _quic = crate::dist::quic::QuicEndpoint::connect(endpoint, remote);
+                    self.logs.push("QUIC transport negotiated with scheduler");
+                }
+                (Err(e), _) => self.logs.push(format!("failed to bind QUIC endpoint: {}", e)),
+                (_, None) => self
+                    .logs
+                    .push("QUIC negotiated but couldn't resolve scheduler address"),
+            }
+        }
+        #[cfg(not(feature = "dist-quic"))]
+        let _ = quic_negotiated;
+        let handler = self.handler;
+        let logs = self.logs;
+        let hooks = self.hooks;
+        let registered = self.registered;
+        let in_progress = self.in_progress;
+        rouille::start_server(self.public_addr, move |request| {
+            router!(request,
+                (POST) (/api/v1/distserver/compile) => {
+                    in_progress.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    let job_id = JobId(0);
+                    let result = run_sandboxed_job(&hooks, job_id, || handler.handle_compile(job_id));
+                    in_progress.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+                    match result {
+                        Ok(()) => {
+                            logs.push("handled compile job");
+                            rouille::Response::text("ok")
+                        }
+                        Err(e) => {
+                            logs.push(format!("compile job failed: {}", e));
+                            rouille::Response::text(e.to_string()).with_status_code(500)
+                        }
+                    }
+                },
+                (GET) (/logs) => {
+                    rouille::Response::json(&logs.tail())
+                },
+                (GET) (/status) => {
+                    let registered_ready = registered.clone();
+                    let registered_snapshot = registered.clone();
+                    let in_progress = in_progress.clone();
+                    sse_response(StatusStream::new(
+                        move || registered_ready.load(std::sync::atomic::Ordering::SeqCst),
+                        move || {
+                            serde_json::to_string(&ServerSelfStatus {
+                                in_progress: in_progress.load(std::sync::atomic::Ordering::SeqCst),
+                                registered: registered_snapshot.load(std::sync::atomic::Ordering::SeqCst),
+                            })
+                            .unwrap()
+                        },
+                        STATUS_POLL_INTERVAL,
+                    ))
+                },
+                _ => rouille::Response::empty_404(),
+            )
+        })
+    }
+}
+
+#[allow(dead_code)]
+fn heartbeat_is_stale(last: Instant, max_age: Duration) -> bool {
+    last.elapsed() > max_age
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn server_id(port: u16) -> ServerId {
+        ServerId::new(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port))
+    }
+
+    fn idle(num_cpus: usize) -> ServerState {
+        ServerState {
+            num_cpus,
+            in_progress: 0,
+            last_heartbeat: Instant::now(),
+            draining: false,
+            quic: false,
+        }
+    }
+
+    fn state_with_servers(servers: Vec<(ServerId, ServerState)>) -> SchedulerState {
+        SchedulerState {
+            servers: Mutex::new(servers.into_iter().collect()),
+            toolchain_cache_size: Mutex::new(0),
+            logs: Arc::new(LogBuffer::new(8)),
+            coalescer: JobCoalescer::new(),
+            start_time: Instant::now(),
+            client_auth: ClientAuth::Insecure,
+            server_auth: ServerAuth::Insecure,
+        }
+    }
+
+    #[test]
+    fn place_job_picks_the_least_loaded_server() {
+        let state = state_with_servers(vec![
+            (
+                server_id(1),
+                ServerState {
+                    in_progress: 3,
+                    ..idle(4)
+                },
+            ),
+            (
+                server_id(2),
+                ServerState {
+                    in_progress: 1,
+                    ..idle(4)
+                },
+            ),
+        ]);
+        assert_eq!(state.place_job().unwrap(), server_id(2));
+    }
+
+    #[test]
+    fn place_job_prefers_lower_load_over_lower_absolute_in_progress_count() {
+        // Server 1 has fewer jobs in absolute terms (1 vs 3) but far less
+        // spare capacity (1/2 == 0.5 load vs 3/8 == 0.375), so the
+        // less-loaded server 2 should still win.
+        let state = state_with_servers(vec![
+            (
+                server_id(1),
+                ServerState {
+                    num_cpus: 2,
+                    in_progress: 1,
+                    ..idle(2)
+                },
+            ),
+            (
+                server_id(2),
+                ServerState {
+                    num_cpus: 8,
+                    in_progress: 3,
+                    ..idle(8)
+                },
+            ),
+        ]);
+        assert_eq!(state.place_job().unwrap(), server_id(2));
+    }
+
+    #[test]
+    fn place_job_skips_draining_servers() {
+        let state = state_with_servers(vec![
+            (
+                server_id(1),
+                ServerState {
+                    draining: true,
+                    ..idle(4)
+                },
+            ),
+            (
+                server_id(2),
+                ServerState {
+                    in_progress: 2,
+                    ..idle(4)
+                },
+            ),
+        ]);
+        assert_eq!(state.place_job().unwrap(), server_id(2));
+    }
+
+    #[test]
+    fn place_job_fails_when_every_server_is_draining() {
+        let state = state_with_servers(vec![(
+            server_id(1),
+            ServerState {
+                draining: true,
+                ..idle(4)
+            },
+        )]);
+        assert!(matches!(state.place_job(), Err(PlacementError::Saturated)));
+    }
+
+    #[test]
+    fn place_job_fails_when_every_server_is_at_capacity() {
+        let state = state_with_servers(vec![(
+            server_id(1),
+            ServerState {
+                in_progress: 4,
+                ..idle(4)
+            },
+        )]);
+        assert!(matches!(state.place_job(), Err(PlacementError::Saturated)));
+    }
+
+    #[test]
+    fn place_job_increments_in_progress_on_the_chosen_server() {
+        let state = state_with_servers(vec![(server_id(1), idle(4))]);
+        state.place_job().unwrap();
+        assert_eq!(
+            state.servers.lock().unwrap()[&server_id(1)].in_progress,
+            1
+        );
+    }
+
+    #[test]
+    fn server_statuses_reports_load_and_draining_per_server() {
+        let state = state_with_servers(vec![(
+            server_id(1),
+            ServerState {
+                num_cpus: 4,
+                in_progress: 2,
+                draining: true,
+                ..idle(4)
+            },
+        )]);
+        let statuses = state.server_statuses();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].num_cpus, 4);
+        assert_eq!(statuses[0].in_progress, 2);
+        assert_eq!(statuses[0].load, 0.5);
+        assert!(statuses[0].draining);
+    }
+
+    #[test]
+    fn register_resets_in_progress_and_draining_for_a_rejoining_server() {
+        let state = state_with_servers(vec![(
+            server_id(1),
+            ServerState {
+                in_progress: 3,
+                draining: true,
+                ..idle(4)
+            },
+        )]);
+        state
+            .register(
+                server_id(1),
+                RegisterServerRequest {
+                    handshake: Handshake::current(),
+                    num_cpus: 4,
+                },
+            )
+            .unwrap();
+        let registered = &state.servers.lock().unwrap()[&server_id(1)];
+        assert_eq!(registered.in_progress, 0);
+        assert!(!registered.draining);
+    }
+}
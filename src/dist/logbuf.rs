@@ -0,0 +1,134 @@
+//! A small bounded in-memory log buffer, so a live scheduler/server can be
+//! asked for its recent output without shelling into a container (or, for
+//! `ServerHandle::Process`/`Ssh` forks, without any container logs at all).
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use log::{Log, Metadata, Record};
+
+/// Keeps the last `capacity` lines pushed to it, dropping the oldest line
+/// once capacity is hit. Cheap to append to and safe to share behind an
+/// `Arc` - every method takes `&self` and locks internally.
+pub struct LogBuffer {
+    capacity: usize,
+    lines: Mutex<VecDeque<String>>,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        LogBuffer {
+            capacity,
+            lines: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn push(&self, line: impl Into<String>) {
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line.into());
+    }
+
+    /// Returns the buffered lines, oldest first.
+    pub fn tail(&self) -> Vec<String> {
+        self.lines.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.lines.lock().unwrap().clear();
+    }
+
+    /// Installs `self` as the process-wide `log` logger, so every
+    /// `log`/`trace!`/`warn!` call anywhere in the binary also lands in
+    /// this buffer (in addition to going through the usual `env_logger`
+    /// formatting to stderr). Only ever succeeds once per process - the
+    /// `cachepot-dist` binary calls this exactly once, right after building
+    /// its `Scheduler`/`Server`.
+    pub fn install(self: &Arc<Self>) -> crate::Result<()> {
+        let inner = env_logger::Builder::from_default_env().build();
+        let max_level = inner.filter();
+        log::set_boxed_logger(Box::new(CapturingLogger {
+            inner,
+            buffer: Arc::clone(self),
+        }))
+        .map_err(|e| anyhow::anyhow!("failed to install logger: {}", e))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
+
+/// A `log::Log` that forwards every record to a [`LogBuffer`] before
+/// passing it on to the normal `env_logger` output.
+struct CapturingLogger {
+    inner: env_logger::Logger,
+    buffer: Arc<LogBuffer>,
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.inner.enabled(record.metadata()) {
+            self.buffer.push(format!(
+                "[{} {}] {}",
+                record.level(),
+                record.target(),
+                record.args()
+            ));
+        }
+        self.inner.log(record);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_oldest_line_past_capacity() {
+        let buf = LogBuffer::new(2);
+        buf.push("a");
+        buf.push("b");
+        buf.push("c");
+        assert_eq!(buf.tail(), vec!["b".to_owned(), "c".to_owned()]);
+    }
+
+    #[test]
+    fn clear_empties_the_buffer() {
+        let buf = LogBuffer::new(2);
+        buf.push("a");
+        buf.clear();
+        assert!(buf.tail().is_empty());
+    }
+
+    #[test]
+    fn capturing_logger_forwards_enabled_records_into_the_buffer() {
+        let buf = Arc::new(LogBuffer::new(4));
+        let inner = env_logger::Builder::new()
+            .filter_level(log::LevelFilter::Trace)
+            .build();
+        let logger = CapturingLogger {
+            inner,
+            buffer: Arc::clone(&buf),
+        };
+        logger.log(
+            &Record::builder()
+                .level(log::Level::Warn)
+                .target("some_target")
+                .args(format_args!("disk almost full"))
+                .build(),
+        );
+        assert_eq!(
+            buf.tail(),
+            vec!["[WARN some_target] disk almost full".to_owned()]
+        );
+    }
+}
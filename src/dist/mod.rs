@@ -0,0 +1,53 @@
+//! Distributed compilation: the scheduler/server protocol and the traits
+//! that tie it to a concrete transport (see [`http`]).
+
+pub mod backoff;
+pub mod coalesce;
+pub mod download;
+pub mod hooks;
+pub mod http;
+pub mod logbuf;
+pub mod proto;
+#[cfg(feature = "dist-quic")]
+pub mod quic;
+pub mod sse;
+pub mod ssh;
+pub mod tls;
+
+use std::net::SocketAddr;
+
+use serde::{Deserialize, Serialize};
+
+/// Identifies a build server by the address it's reachable on.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub struct ServerId(SocketAddr);
+
+impl ServerId {
+    pub fn new(addr: SocketAddr) -> Self {
+        ServerId(addr)
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.0
+    }
+}
+
+/// Identifies a single compile job submitted to the scheduler.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub struct JobId(pub u64);
+
+/// Snapshot returned by the scheduler's status endpoint, polled by
+/// `DistSystem::scheduler_status` in the integration harness.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SchedulerStatusResult {
+    pub num_servers: usize,
+    pub num_cpus: usize,
+    pub in_progress: usize,
+}
+
+/// Implemented by whatever is listening on a build server's incoming
+/// connection - handed to [`http::Server::new`] so the harness can swap in
+/// a custom in-process handler via `DistSystem::add_custom_server`.
+pub trait ServerIncoming: Send + Sync {
+    fn handle_compile(&self, job_id: JobId) -> crate::Result<()>;
+}
@@ -0,0 +1,132 @@
+//! Protocol version and capability negotiation for the dist handshake.
+//!
+//! A build server advertises a [`Handshake`] when it registers with the
+//! scheduler. The scheduler compares its version against compiled-in bounds
+//! so an old/new binary gets a clear, typed rejection instead of a
+//! `bincode` decode error buried several layers down, and replies with the
+//! negotiated capabilities only: it does not send its own version back, so
+//! the server does no version check of its own.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The protocol version this build of cachepot speaks.
+pub const CURRENT_PROTOCOL_VERSION: u32 = 2;
+/// The oldest protocol version this build will still talk to.
+pub const MIN_SUPPORTED_PROTOCOL_VERSION: u32 = 1;
+
+/// A named, optional protocol feature. Both ends must advertise a
+/// capability before either will use it, so new transports/codecs can ship
+/// without breaking old peers.
+pub mod capabilities {
+    pub const QUIC_TRANSPORT: &str = "quic-transport";
+    pub const RANGE_DOWNLOADS: &str = "range-downloads";
+}
+
+/// Advertised by a build server when it registers with the scheduler.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Handshake {
+    pub version: u32,
+    pub capabilities: HashSet<String>,
+}
+
+impl Handshake {
+    /// The handshake this build of cachepot advertises to peers.
+    pub fn current() -> Self {
+        Handshake {
+            version: CURRENT_PROTOCOL_VERSION,
+            capabilities: HashSet::from([capabilities::RANGE_DOWNLOADS.to_owned()]),
+        }
+    }
+
+    pub fn with_capability(mut self, cap: &str) -> Self {
+        self.capabilities.insert(cap.to_owned());
+        self
+    }
+
+    /// As [`Self::current`], additionally advertising
+    /// [`capabilities::QUIC_TRANSPORT`] when `want_quic` is set and this
+    /// build was compiled with `dist-quic`. `want_quic` is an explicit
+    /// opt-in (rather than advertising it under `cfg(feature =
+    /// "dist-quic")` alone) so a peer with the feature compiled in but
+    /// configured for [`crate::config::Transport::Https`] isn't offered a
+    /// transport it never asked for.
+    pub fn current_with_quic(want_quic: bool) -> Self {
+        let mut handshake = Self::current();
+        if want_quic && cfg!(feature = "dist-quic") {
+            handshake
+                .capabilities
+                .insert(capabilities::QUIC_TRANSPORT.to_owned());
+        }
+        handshake
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ProtocolError {
+    #[error("incompatible protocol version: we speak {ours}, peer speaks {theirs}")]
+    IncompatibleProtocol { ours: u32, theirs: u32 },
+}
+
+/// Checks `theirs` against our supported range and, if compatible, returns
+/// the capabilities both sides support (the intersection).
+pub fn negotiate(theirs: &Handshake) -> Result<HashSet<String>, ProtocolError> {
+    if theirs.version < MIN_SUPPORTED_PROTOCOL_VERSION || theirs.version > CURRENT_PROTOCOL_VERSION
+    {
+        return Err(ProtocolError::IncompatibleProtocol {
+            ours: CURRENT_PROTOCOL_VERSION,
+            theirs: theirs.version,
+        });
+    }
+    let ours = Handshake::current();
+    Ok(ours
+        .capabilities
+        .intersection(&theirs.capabilities)
+        .cloned()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_too_new_peer() {
+        let theirs = Handshake {
+            version: CURRENT_PROTOCOL_VERSION + 1,
+            capabilities: HashSet::new(),
+        };
+        assert!(matches!(
+            negotiate(&theirs),
+            Err(ProtocolError::IncompatibleProtocol { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_too_old_peer() {
+        let theirs = Handshake {
+            version: MIN_SUPPORTED_PROTOCOL_VERSION - 1,
+            capabilities: HashSet::new(),
+        };
+        assert!(negotiate(&theirs).is_err());
+    }
+
+    #[test]
+    fn accepts_supported_peer_and_intersects_capabilities() {
+        let theirs = Handshake::current().with_capability(capabilities::RANGE_DOWNLOADS);
+        let caps = negotiate(&theirs).unwrap();
+        assert_eq!(
+            caps,
+            HashSet::from([capabilities::RANGE_DOWNLOADS.to_owned()])
+        );
+    }
+
+    #[test]
+    fn current_with_quic_only_advertises_quic_when_requested() {
+        assert!(!Handshake::current_with_quic(false)
+            .capabilities
+            .contains(capabilities::QUIC_TRANSPORT));
+    }
+}
@@ -0,0 +1,41 @@
+//! Optional QUIC/HTTP3 transport for toolchain and artifact transfer.
+//!
+//! HTTPS over TCP suffers from head-of-line blocking and per-connection
+//! setup cost over high-latency or lossy links between a client, the
+//! scheduler, and geographically distant build servers. When both ends
+//! advertise [`crate::dist::proto::capabilities::QUIC_TRANSPORT`] in the
+//! handshake, large toolchain uploads/downloads can use independent QUIC
+//! streams instead, so one stalled transfer doesn't block the others.
+//!
+//! Gated behind the `dist-quic` feature; without it, everything falls back
+//! to the plain HTTP/1.1+TLS transport in [`super::http`].
+
+use std::net::SocketAddr;
+
+/// An established QUIC endpoint to a scheduler or build server, used in
+/// place of a `reqwest::Client` for large transfers once negotiated.
+pub struct QuicEndpoint {
+    endpoint: quinn::Endpoint,
+    remote: SocketAddr,
+}
+
+impl QuicEndpoint {
+    pub fn connect(endpoint: quinn::Endpoint, remote: SocketAddr) -> Self {
+        QuicEndpoint { endpoint, remote }
+    }
+
+    /// Sends `data` on a fresh unidirectional stream, independent of any
+    /// other in-flight transfer on this endpoint.
+    pub async fn send_stream(&self, data: &[u8]) -> crate::Result<()> {
+        let connection = self.endpoint.connect(self.remote, "cachepot-dist")?.await?;
+        let mut send = connection.open_uni().await?;
+        send.write_all(data).await?;
+        send.finish().await?;
+        Ok(())
+    }
+}
+
+/// Whether this build of cachepot was compiled with QUIC support.
+pub fn is_available() -> bool {
+    cfg!(feature = "dist-quic")
+}
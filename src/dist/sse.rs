@@ -0,0 +1,104 @@
+//! Minimal Server-Sent-Events support for the scheduler/server `/status`
+//! endpoint: a push-based readiness and live-state stream, so clients and
+//! dashboards can subscribe for updates instead of polling.
+
+use std::io::Read;
+use std::time::Duration;
+
+/// Formats one SSE frame: `event: <name>\ndata: <payload>\n\n`.
+pub fn frame(event: &str, data: &str) -> Vec<u8> {
+    format!("event: {}\ndata: {}\n\n", event, data).into_bytes()
+}
+
+/// An unbounded SSE [`Read`] source: blocks until `is_ready` returns true,
+/// emits a single `ready` event with `snapshot()`'s value, then an
+/// `update` event carrying a fresh `snapshot()` every `poll_interval`
+/// until the client disconnects (detected by rouille failing to write to
+/// the socket, which drops this reader).
+pub struct StatusStream<R, S> {
+    is_ready: R,
+    snapshot: S,
+    poll_interval: Duration,
+    sent_ready: bool,
+    pending: std::collections::VecDeque<u8>,
+}
+
+impl<R, S> StatusStream<R, S>
+where
+    R: Fn() -> bool,
+    S: Fn() -> String,
+{
+    pub fn new(is_ready: R, snapshot: S, poll_interval: Duration) -> Self {
+        StatusStream {
+            is_ready,
+            snapshot,
+            poll_interval,
+            sent_ready: false,
+            pending: std::collections::VecDeque::new(),
+        }
+    }
+
+    fn next_frame(&mut self) -> Vec<u8> {
+        if !self.sent_ready {
+            while !(self.is_ready)() {
+                std::thread::sleep(self.poll_interval);
+            }
+            self.sent_ready = true;
+            return frame("ready", &(self.snapshot)());
+        }
+        std::thread::sleep(self.poll_interval);
+        frame("update", &(self.snapshot)())
+    }
+}
+
+impl<R, S> Read for StatusStream<R, S>
+where
+    R: Fn() -> bool,
+    S: Fn() -> String,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pending.is_empty() {
+            let frame = self.next_frame();
+            self.pending.extend(frame);
+        }
+        let n = self.pending.len().min(buf.len());
+        for (i, b) in self.pending.drain(..n).enumerate() {
+            buf[i] = b;
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_formats_as_sse() {
+        assert_eq!(frame("ready", "{}"), b"event: ready\ndata: {}\n\n".to_vec());
+    }
+
+    #[test]
+    fn waits_for_readiness_before_first_frame() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        let ready = Arc::new(AtomicBool::new(false));
+        let mut stream = StatusStream::new(
+            {
+                let ready = ready.clone();
+                move || ready.load(Ordering::SeqCst)
+            },
+            || "{}".to_owned(),
+            Duration::from_millis(1),
+        );
+        // Flip readiness on a timer so `read` has to actually wait for it.
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            ready.store(true, Ordering::SeqCst);
+        });
+        let mut buf = [0u8; 256];
+        let n = stream.read(&mut buf).unwrap();
+        assert!(String::from_utf8_lossy(&buf[..n]).starts_with("event: ready\n"));
+    }
+}
@@ -0,0 +1,163 @@
+//! Provisioning a build server over a plain SSH pipe, as an alternative to
+//! the Docker-container path: no container runtime is required on the
+//! remote host, just an SSH server and the `cachepot-dist` binary.
+
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::time::Duration;
+
+use anyhow::Context;
+
+use crate::dist::hooks::{run_hooks, HookPhase, SandboxHooks};
+
+/// The line `cachepot-dist server` prints to stdout with its configured
+/// public address, e.g. `LISTENING 10.0.0.5:12345`. It is printed just
+/// before the server registers with the scheduler and binds its socket.
+const STARTUP_BANNER_PREFIX: &str = "LISTENING ";
+
+/// A `cachepot-dist server` process running on a remote host, supervised
+/// over an SSH pipe.
+pub struct SshServer {
+    host: String,
+    ssh_command_prefix: Vec<String>,
+    remote_config_path: String,
+    child: Child,
+    addr: std::net::SocketAddr,
+}
+
+impl SshServer {
+    /// Opens an SSH connection to `host` (via `ssh_command_prefix`, e.g.
+    /// `["ssh", "-i", "key.pem"]`), launches `cachepot-dist server` with
+    /// `remote_config_path`, and blocks until the remote prints its startup
+    /// banner so its address can be parsed out. The banner precedes the
+    /// remote's bind, so the remote is not yet known to be listening when
+    /// this returns.
+    ///
+    /// Runs `hooks`' pre-create hooks before spawning the SSH process and
+    /// its post-start hooks once the banner has been read.
+    pub fn spawn(
+        host: &str,
+        ssh_command_prefix: &[&str],
+        remote_config_path: &str,
+        hooks: &SandboxHooks,
+    ) -> crate::Result<Self> {
+        let state = format!(r#"{{"host":{:?}}}"#, host);
+        run_hooks(hooks, HookPhase::PreCreate, &state)?;
+
+        let (program, prefix_args) = ssh_command_prefix
+            .split_first()
+            .ok_or_else(|| anyhow::anyhow!("ssh_command_prefix must not be empty"))?;
+
+        let mut child = Command::new(program)
+            .args(prefix_args)
+            .arg(host)
+            .arg(format!(
+                "cachepot-dist server --config {} --print-listening-addr",
+                remote_config_path
+            ))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let addr = read_startup_banner(child.stdout.as_mut().unwrap())?;
+
+        run_hooks(hooks, HookPhase::PostStart, &state)?;
+
+        Ok(SshServer {
+            host: host.to_owned(),
+            ssh_command_prefix: ssh_command_prefix.iter().map(|s| s.to_string()).collect(),
+            remote_config_path: remote_config_path.to_owned(),
+            child,
+            addr,
+        })
+    }
+
+    pub fn addr(&self) -> std::net::SocketAddr {
+        self.addr
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+}
+
+fn read_startup_banner(stdout: &mut ChildStdout) -> crate::Result<std::net::SocketAddr> {
+    let mut reader = BufReader::new(stdout);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            anyhow::bail!("remote process exited before printing its startup banner");
+        }
+        if let Some(addr) = line.trim_end().strip_prefix(STARTUP_BANNER_PREFIX) {
+            return addr
+                .parse()
+                .with_context(|| format!("couldn't parse remote startup banner {:?}", line));
+        }
+    }
+}
+
+/// Tears down a remote server the same way `DistSystem::drop` tears down
+/// local ones: pre-stop hooks, then a remote `pkill` for the actual
+/// `cachepot-dist server` process (killing the local `ssh` client alone
+/// doesn't reliably reach it), then SIGINT/SIGKILL on the local `ssh`
+/// client itself, draining any remaining stdout/stderr for diagnostics.
+pub fn teardown(mut server: SshServer, hooks: &SandboxHooks) -> crate::Result<()> {
+    use nix::sys::signal::{kill, Signal};
+    use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+    use nix::unistd::Pid;
+
+    let state = format!(r#"{{"host":{:?}}}"#, server.host);
+    run_hooks(hooks, HookPhase::PreStop, &state)?;
+
+    // Killing the local `ssh` client's pid only tears down the SSH channel;
+    // without a pty/signal-forwarding, that doesn't reliably reach the
+    // remote `cachepot-dist server` process, which would otherwise leak.
+    // Ask the remote host directly to kill it (identified by its config
+    // path, which is unique per spawn) before tearing down the local ssh
+    // client below.
+    if let Some((program, prefix_args)) = server.ssh_command_prefix.split_first() {
+        let remote_kill = Command::new(program)
+            .args(prefix_args)
+            .arg(&server.host)
+            .arg(format!(
+                "pkill -f 'cachepot-dist server --config {}'",
+                server.remote_config_path
+            ))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        if let Err(e) = remote_kill {
+            eprintln!("failed to run remote pkill on {}: {}", server.host, e);
+        }
+    }
+
+    let pid = Pid::from_raw(server.child.id() as i32);
+    let _ = kill(pid, Signal::SIGINT);
+    std::thread::sleep(Duration::from_millis(100));
+
+    let still_alive = matches!(
+        waitpid(pid, Some(WaitPidFlag::WNOHANG)),
+        Ok(WaitStatus::StillAlive)
+    );
+    if still_alive {
+        let _ = kill(pid, Signal::SIGKILL);
+        let _ = waitpid(pid, None);
+    }
+
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+    if let Some(mut out) = server.child.stdout.take() {
+        let _ = out.read_to_string(&mut stdout);
+    }
+    if let Some(mut err) = server.child.stderr.take() {
+        let _ = err.read_to_string(&mut stderr);
+    }
+    eprintln!(
+        "SSH server on {} torn down\n[stdout]\n{}\n[stderr]\n{}",
+        server.host, stdout, stderr
+    );
+    Ok(())
+}
@@ -0,0 +1,9 @@
+//! Crate-wide error type aliases.
+//!
+//! Most of cachepot propagates errors with `anyhow`; subsystems that need
+//! callers to match on *why* something failed (protocol negotiation, exit
+//! codes, timeouts) define their own `thiserror` enum instead and convert
+//! into this type at the boundary.
+
+pub use anyhow::Error;
+pub type Result<T> = anyhow::Result<T>;
@@ -0,0 +1,82 @@
+//! Category-specific exit codes for the `cachepot` client.
+//!
+//! By default the client still collapses every failure to exit code `1`
+//! (or `255` for an internal panic), matching every CI script written
+//! against the legacy behavior. Setting `CACHEPOT_DETAILED_EXIT_CODES` to
+//! `1` or `true` opts into distinct, documented codes per [`FailureReason`]
+//! so scripts can tell *why* a build failed without scraping stderr. There
+//! is no config-file setting for this; only the environment variable is
+//! read.
+
+use serde::{Deserialize, Serialize};
+
+/// Why a `cachepot` invocation failed. Mirrored into the `--stats-format=json`
+/// output as `last_failure_reason` (see [`crate::server::ServerInfo`]) so
+/// `get_stats` consumers can assert on it
+/// programmatically, independent of whether detailed exit codes are enabled.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum FailureReason {
+    /// The wrapped compiler invocation itself failed (e.g. a syntax error).
+    CompilerError,
+    /// Couldn't reach the dist scheduler at all.
+    SchedulerUnreachable,
+    /// Reached the scheduler, but no build server could take the job.
+    NoBuildServerAvailable,
+    /// The local disk/cache config was invalid or unusable.
+    LocalCacheError,
+    /// cachepot itself panicked.
+    InternalPanic,
+}
+
+impl FailureReason {
+    /// The exit code used when detailed exit codes are enabled. Codes
+    /// start at 2 so they never collide with the legacy "generic failure"
+    /// code of `1`.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            FailureReason::CompilerError => 2,
+            FailureReason::SchedulerUnreachable => 3,
+            FailureReason::NoBuildServerAvailable => 4,
+            FailureReason::LocalCacheError => 5,
+            FailureReason::InternalPanic => 70,
+        }
+    }
+}
+
+/// The legacy, single-code exit status used unless detailed exit codes are
+/// explicitly opted into.
+pub const LEGACY_FAILURE_EXIT_CODE: i32 = 1;
+pub const LEGACY_PANIC_EXIT_CODE: i32 = 255;
+
+/// Whether the client should map failures to [`FailureReason::exit_code`]
+/// instead of always returning [`LEGACY_FAILURE_EXIT_CODE`].
+pub fn detailed_exit_codes_enabled() -> bool {
+    std::env::var("CACHEPOT_DETAILED_EXIT_CODES")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// The process exit code to use for a given failure, honoring the
+/// opt-in/legacy distinction.
+pub fn exit_code_for(reason: FailureReason) -> i32 {
+    if detailed_exit_codes_enabled() {
+        reason.exit_code()
+    } else if reason == FailureReason::InternalPanic {
+        LEGACY_PANIC_EXIT_CODE
+    } else {
+        LEGACY_FAILURE_EXIT_CODE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_mode_collapses_to_legacy_failure_and_panic_codes() {
+        std::env::remove_var("CACHEPOT_DETAILED_EXIT_CODES");
+        assert_eq!(exit_code_for(FailureReason::CompilerError), LEGACY_FAILURE_EXIT_CODE);
+        assert_eq!(exit_code_for(FailureReason::SchedulerUnreachable), LEGACY_FAILURE_EXIT_CODE);
+        assert_eq!(exit_code_for(FailureReason::InternalPanic), LEGACY_PANIC_EXIT_CODE);
+    }
+}
@@ -0,0 +1,13 @@
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate rouille;
+
+pub mod config;
+pub mod dist;
+pub mod errors;
+pub mod exitcode;
+pub mod server;
+pub mod util;
+
+pub use crate::errors::{Error, Result};
@@ -0,0 +1,25 @@
+//! The local cachepot daemon: accepts compile requests from the `cachepot`
+//! client binary over a local socket and dispatches them to a cache and/or
+//! the dist cluster.
+
+use serde::{Deserialize, Serialize};
+
+use crate::exitcode::FailureReason;
+
+/// Stats reported by `cachepot --show-stats`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub stats: ServerStats,
+    /// Why the most recent compile request failed, if it did. Lets
+    /// `--stats-format=json` consumers assert on the failure category
+    /// without depending on detailed exit codes being enabled.
+    pub last_failure_reason: Option<FailureReason>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ServerStats {
+    pub compile_requests: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub cache_errors: u64,
+}
@@ -0,0 +1,23 @@
+//! Small helpers shared across the crate.
+
+use std::ffi::OsStr;
+
+/// Thin wrapper around `std::fs` kept so call sites can be instrumented or
+/// swapped out (e.g. in tests) without touching every call site directly.
+pub mod fs {
+    pub use std::fs::{create_dir, File};
+}
+
+/// Extensions to `OsStr` that std doesn't provide.
+pub trait OsStrExt {
+    /// Returns true if `self` starts with the given prefix. The comparison
+    /// is made on `self`'s lossy UTF-8 conversion, so bytes that aren't
+    /// valid UTF-8 are compared as U+FFFD.
+    fn starts_with(&self, prefix: &str) -> bool;
+}
+
+impl OsStrExt for OsStr {
+    fn starts_with(&self, prefix: &str) -> bool {
+        self.to_string_lossy().starts_with(prefix)
+    }
+}
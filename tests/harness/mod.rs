@@ -1,5 +1,6 @@
 #[cfg(any(feature = "dist-client", feature = "dist-server"))]
 use cachepot::config::HTTPUrl;
+use cachepot::dist::backoff::Backoff;
 use cachepot::dist::{self, SchedulerStatusResult, ServerId};
 use cachepot::server::ServerInfo;
 use cachepot::util::fs;
@@ -147,6 +148,8 @@ pub fn cachepot_client_cfg(tmpdir: &Path) -> cachepot::config::FileConfig {
             toolchains: vec![],
             toolchain_cache_size: TC_CACHE_SIZE,
             rewrite_includes_only: false, // TODO
+            // Tests run on loopback and don't need a bound.
+            net_timeout_ms: 0,
         },
     }
 }
@@ -165,6 +168,7 @@ fn cachepot_server_cfg(
     tmpdir: &Path,
     scheduler_url: HTTPUrl,
     server_ip: IpAddr,
+    transport: cachepot::config::Transport,
 ) -> cachepot::config::server::Config {
     let relpath = "server-cache";
     fs::create_dir(tmpdir.join(relpath)).unwrap();
@@ -173,6 +177,7 @@ fn cachepot_server_cfg(
         builder: cachepot::config::server::BuilderType::Overlay {
             build_dir: BUILD_DIR_CONTAINER_PATH.into(),
             bwrap_path: DIST_IMAGE_BWRAP_PATH.into(),
+            hooks: Default::default(),
         },
         cache_dir: Path::new(CONFIGS_CONTAINER_PATH).join(relpath),
         public_addr: SocketAddr::new(server_ip, SERVER_PORT),
@@ -181,20 +186,24 @@ fn cachepot_server_cfg(
             token: DIST_SERVER_TOKEN.to_owned(),
         },
         toolchain_cache_size: TC_CACHE_SIZE,
+        transport,
+        // Tests run on loopback and don't need a bound.
+        net_timeout_ms: 0,
+        // dangerously_insecure: tests use self-signed certs over loopback.
+        auth: Default::default(),
     }
 }
 
-// TODO: this is copied from the cachepot-dist binary - it's not clear where would be a better place to put the
-// code so that it can be included here
 #[cfg(feature = "dist-server")]
 fn create_server_token(server_id: ServerId, auth_token: &str) -> String {
-    format!("{} {}", server_id.addr(), auth_token)
+    dist::http::server_auth_token(server_id, auth_token)
 }
 
 #[cfg(feature = "dist-server")]
 pub enum ServerHandle {
     Container { cid: String, url: HTTPUrl },
     Process { pid: Pid, url: HTTPUrl },
+    Ssh { ssh: dist::ssh::SshServer, url: HTTPUrl },
 }
 
 // #[cfg(feature = "dist-server")]
@@ -278,6 +287,7 @@ pub struct DistSystem {
     server_names: Vec<String>,
     server_pids: Vec<Pid>,
     servers: Vec<Arc<ServerHandle>>,
+    transport: cachepot::config::Transport,
 }
 
 #[cfg(feature = "dist-server")]
@@ -316,9 +326,18 @@ impl DistSystem {
             server_names: vec![],
             server_pids: vec![],
             servers: vec![],
+            transport: cachepot::config::Transport::Https,
         }
     }
 
+    /// Selects the toolchain/artifact transport used by servers added
+    /// afterwards, e.g. to exercise the QUIC path in a test.
+    #[allow(unused)]
+    pub fn with_transport(mut self, transport: cachepot::config::Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
     pub fn add_scheduler(&mut self) {
         let scheduler_cfg_relpath = "scheduler-cfg.json";
         let scheduler_cfg_path = self.tmpdir.join(scheduler_cfg_relpath);
@@ -445,7 +464,8 @@ impl DistSystem {
         check_output(&output);
 
         let server_ip = self.container_ip(&server_name);
-        let server_cfg = cachepot_server_cfg(&self.tmpdir, self.scheduler_url(), server_ip);
+        let server_cfg =
+            cachepot_server_cfg(&self.tmpdir, self.scheduler_url(), server_ip, self.transport);
         fs::File::create(&server_cfg_path)
             .unwrap()
             .write_all(&serde_json::to_vec(&server_cfg).unwrap())
@@ -497,6 +517,44 @@ impl DistSystem {
         handle
     }
 
+    /// Provisions a build server on a remote host over SSH instead of a
+    /// Docker container, for deployments where every node can't run a
+    /// container runtime.
+    pub fn add_ssh_server(
+        &mut self,
+        host: &str,
+        ssh_command_prefix: &[&str],
+    ) -> Arc<ServerHandle> {
+        let server_cfg_relpath = format!("server-cfg-ssh-{}.json", self.server_names.len());
+        let server_cfg_path = self.tmpdir.join(&server_cfg_relpath);
+        // The remote host is assumed to share a filesystem path layout with
+        // the test harness (e.g. an NFS-mounted config dir), mirroring how
+        // container configs are handed over via a bind mount.
+        let server_cfg = cachepot_server_cfg(
+            &self.tmpdir,
+            self.scheduler_url(),
+            self.host_interface_ip(),
+            self.transport,
+        );
+        fs::File::create(&server_cfg_path)
+            .unwrap()
+            .write_all(&serde_json::to_vec(&server_cfg).unwrap())
+            .unwrap();
+
+        let ssh = dist::ssh::SshServer::spawn(
+            host,
+            ssh_command_prefix,
+            server_cfg_path.to_str().unwrap(),
+            &Default::default(),
+        )
+        .unwrap();
+        let url = HTTPUrl::from_url(reqwest::Url::parse(&format!("https://{}", ssh.addr())).unwrap());
+        let handle = Arc::new(ServerHandle::Ssh { ssh, url });
+        self.wait_server_ready(&handle);
+        self.servers.push(Arc::clone(&handle));
+        handle
+    }
+
     pub fn restart_server(&mut self, handle: &ServerHandle) {
         match handle {
             ServerHandle::Container { cid, url: _ } => {
@@ -510,15 +568,19 @@ impl DistSystem {
                 // TODO: pretty easy, just no need yet
                 panic!("restart not yet implemented for pids")
             }
+            ServerHandle::Ssh { ssh: _, url: _ } => {
+                // TODO: pretty easy, just no need yet
+                panic!("restart not yet implemented for ssh servers")
+            }
         }
         self.wait_server_ready(handle)
     }
 
     pub fn wait_server_ready(&mut self, handle: &ServerHandle) {
         let url = match handle {
-            ServerHandle::Container { cid: _, url } | ServerHandle::Process { pid: _, url } => {
-                url.clone()
-            }
+            ServerHandle::Container { cid: _, url }
+            | ServerHandle::Process { pid: _, url }
+            | ServerHandle::Ssh { ssh: _, url } => url.clone(),
         };
         wait_for_http(url, Duration::from_millis(100), MAX_STARTUP_WAIT);
         wait_for(
@@ -631,6 +693,21 @@ impl Drop for DistSystem {
         let mut outputs = vec![];
         let mut exits = vec![];
 
+        // Best-effort: fetch the in-memory ring buffer over /logs before
+        // tearing anything down. This is the only diagnostics available at
+        // all for `ServerHandle::Process`/`Ssh`, which have no `docker logs`
+        // to fall back on.
+        for server in self.servers.iter() {
+            let url = match server.as_ref() {
+                ServerHandle::Container { url, .. }
+                | ServerHandle::Process { url, .. }
+                | ServerHandle::Ssh { url, .. } => url.clone(),
+            };
+            if let Ok(tail) = dist::http::fetch_logs(&url.to_url()) {
+                eprintln!("/logs from {}:\n{}", url, tail.join("\n"));
+            }
+        }
+
         if let Some(scheduler_name) = self.scheduler_name.as_ref() {
             droperr!(Command::new(CONTAINER_RUNTIME)
                 .args(&["logs", scheduler_name])
@@ -685,6 +762,12 @@ impl Drop for DistSystem {
             }
         }
 
+        for handle in std::mem::take(&mut self.servers) {
+            if let Ok(ServerHandle::Ssh { ssh, url: _ }) = Arc::try_unwrap(handle) {
+                droperr!(dist::ssh::teardown(ssh, &Default::default()).map_err(|e| e.to_string()));
+            }
+        }
+
         for (
             container,
             Output {
@@ -749,33 +832,64 @@ fn check_output(output: &Output) {
 
 #[cfg(feature = "dist-server")]
 fn wait_for_http(url: HTTPUrl, interval: Duration, max_wait: Duration) {
-    // TODO: after upgrading to reqwest >= 0.9, use 'danger_accept_invalid_certs' and stick with that rather than tcp
+    // A genuine "ready" SSE event from `/status` (see `dist::http::urls`)
+    // is a much better readiness signal than "did the socket accept a
+    // connection" - it confirms the scheduler/server is actually serving,
+    // not just that something is listening on the port.
+    //
+    // TODO: once the scheduler/server listener itself terminates TLS,
+    // build this probe's client with `dist::tls::build_client` (with
+    // `dangerously_insecure: true`, as tests do today) instead of a plain
+    // client, so this doubles as the HTTPS probe too.
     wait_for(
-        || {
-            let url = url.to_url();
-            let url = url.socket_addrs(|| None).unwrap();
-            match net::TcpStream::connect(url.as_slice()) {
-                Ok(_) => Ok(()),
-                Err(e) => Err(e.to_string()),
-            }
-        },
+        || wait_for_status_ready(&url).map_err(|e| e.to_string()),
         interval,
         max_wait,
     )
 }
 
+#[cfg(feature = "dist-server")]
+fn wait_for_status_ready(url: &HTTPUrl) -> anyhow::Result<()> {
+    use std::io::{BufRead, BufReader};
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_millis(500))
+        .build()?;
+    let response = client.get(dist::http::urls::status(&url.to_url())).send()?;
+    let mut reader = BufReader::new(response);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            anyhow::bail!("connection closed before a ready event was received");
+        }
+        if line.trim_end() == "event: ready" {
+            return Ok(());
+        }
+    }
+}
+
+// Retries are spaced out with full jitter (rather than a fixed interval) so
+// many tests starting containers at once don't all hammer the
+// scheduler/server at the same instant. Each sleep is sampled uniformly from
+// `[0, interval]`: `interval` is both the base and the ceiling of the
+// backoff, so the delay does not grow between attempts. Bounded by elapsed
+// wall-clock time against `max_wait`, not by attempt count - `max_wait` caps
+// the *overall* wait, it isn't also the per-attempt delay ceiling.
 fn wait_for<F: Fn() -> Result<(), String>>(f: F, interval: Duration, max_wait: Duration) {
-    let start = Instant::now();
-    let mut lasterr;
+    let backoff = Backoff::new(interval, interval, u32::MAX);
+    let deadline = Instant::now() + max_wait;
+    let mut attempt = 0;
     loop {
         match f() {
             Ok(()) => return,
-            Err(e) => lasterr = e,
-        }
-        if start.elapsed() > max_wait {
-            break;
+            Err(e) => {
+                if Instant::now() >= deadline {
+                    panic!("wait timed out, last error result: {}", e);
+                }
+                std::thread::sleep(backoff.delay_for_attempt(attempt));
+                attempt += 1;
+            }
         }
-        thread::sleep(interval)
     }
-    panic!("wait timed out, last error result: {}", lasterr)
 }